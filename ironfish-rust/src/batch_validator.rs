@@ -0,0 +1,272 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use bellman::groth16;
+use bls12_381::{Bls12, G2Prepared, Scalar};
+use group::Curve;
+use pairing::{MillerLoopResult, MultiMillerLoop};
+use rand::{rngs::OsRng, Rng};
+use std::ops::Neg;
+
+use crate::Sapling;
+
+// One proof plus the public inputs it was generated against, waiting to be folded into a
+// batch verification for a particular circuit.
+struct QueuedProof {
+    proof: groth16::Proof<Bls12>,
+    public_inputs: Vec<Scalar>,
+}
+
+/// Verifies many Groth16 proofs against the same `Sapling` with one pairing check per circuit
+/// instead of one independent check per proof, which matters when validating a full block of transactions.
+///
+/// Proofs are queued per-circuit (spend/output/create-asset/mint-asset), since each circuit has
+/// its own `VerifyingKey`. `validate` folds each circuit's queue into a single multi-Miller
+/// loop plus one final exponentiation by scaling every proof's terms by a fresh random scalar
+/// before accumulating them, so a single bad proof can't cancel out against a good one.
+#[derive(Default)]
+pub struct BatchValidator {
+    spend_queue: Vec<QueuedProof>,
+    output_queue: Vec<QueuedProof>,
+    create_asset_queue: Vec<QueuedProof>,
+    mint_asset_queue: Vec<QueuedProof>,
+}
+
+impl BatchValidator {
+    pub fn new() -> Self {
+        BatchValidator::default()
+    }
+
+    pub fn queue_spend(&mut self, proof: groth16::Proof<Bls12>, public_inputs: Vec<Scalar>) {
+        self.spend_queue.push(QueuedProof {
+            proof,
+            public_inputs,
+        });
+    }
+
+    pub fn queue_output(&mut self, proof: groth16::Proof<Bls12>, public_inputs: Vec<Scalar>) {
+        self.output_queue.push(QueuedProof {
+            proof,
+            public_inputs,
+        });
+    }
+
+    pub fn queue_create_asset(&mut self, proof: groth16::Proof<Bls12>, public_inputs: Vec<Scalar>) {
+        self.create_asset_queue.push(QueuedProof {
+            proof,
+            public_inputs,
+        });
+    }
+
+    pub fn queue_mint_asset(&mut self, proof: groth16::Proof<Bls12>, public_inputs: Vec<Scalar>) {
+        self.mint_asset_queue.push(QueuedProof {
+            proof,
+            public_inputs,
+        });
+    }
+
+    /// Verify every queued proof against `sapling`. Returns `false` if any single proof in any
+    /// queue is invalid.
+    ///
+    /// If the batch fails and the caller needs to know which proof was bad, fall back to
+    /// verifying each queue's proofs one at a time with `groth16::verify_proof`.
+    pub fn validate(&self, sapling: &Sapling) -> bool {
+        Self::validate_queue(&self.spend_queue, sapling.spend_verifying_key_raw())
+            && Self::validate_queue(&self.output_queue, sapling.receipt_verifying_key_raw())
+            && Self::validate_queue(
+                &self.create_asset_queue,
+                sapling.create_asset_verifying_key_raw(),
+            )
+            && Self::validate_queue(
+                &self.mint_asset_queue,
+                sapling.mint_asset_verifying_key_raw(),
+            )
+    }
+
+    // Fold every proof in `queue` into a single aggregate pairing check, scaling each proof's `A`
+    // and `C` terms (and its contribution to the input accumulator) by a fresh random non-zero
+    // scalar `r_i`. For a single proof the Groth16 check is:
+    //
+    //   e(A, B) = e(alpha, beta) * e(inputs * gamma^-1, gamma) * e(C, delta)
+    //
+    // Batched across `n` proofs with independent `r_i`, the left-hand side becomes one multi-Miller
+    // loop plus one final exponentiation instead of `n` of each:
+    //
+    //   prod_i [ e(r_i * A_i, B_i) * e(r_i * acc_i, -gamma) * e(r_i * C_i, -delta) ] == alpha_beta^R
+    //
+    // where `acc_i` is proof i's input accumulator, `R = sum_i r_i`, and `alpha_beta` is
+    // `e(alpha, beta)`.
+    //
+    // `bellman`'s `PreparedVerifyingKey` keeps its negated-gamma/delta `G2Prepared` and its
+    // `alpha_g1_beta_g2` as private fields, so they can't be read from outside the crate. Derive
+    // the same terms here from the public `VerifyingKey` on the circuit's `Parameters` instead.
+    fn validate_queue(queue: &[QueuedProof], vk: &groth16::VerifyingKey<Bls12>) -> bool {
+        if queue.is_empty() {
+            return true;
+        }
+
+        let neg_gamma_g2 = G2Prepared::from(vk.gamma_g2.neg());
+        let neg_delta_g2 = G2Prepared::from(vk.delta_g2.neg());
+        let alpha_beta = bls12_381::pairing(&vk.alpha_g1, &vk.beta_g2);
+
+        let mut rng = OsRng;
+        let mut terms = Vec::with_capacity(queue.len() * 3);
+        let mut r_sum = Scalar::zero();
+
+        for queued in queue {
+            if queued.public_inputs.len() + 1 != vk.ic.len() {
+                return false;
+            }
+
+            let r = loop {
+                let candidate = Scalar::from_raw([rng.gen(), rng.gen(), rng.gen(), rng.gen()]);
+                if !bool::from(candidate.is_zero()) {
+                    break candidate;
+                }
+            };
+
+            let mut acc = vk.ic[0].to_curve();
+            for (input, base) in queued.public_inputs.iter().zip(vk.ic.iter().skip(1)) {
+                acc += base * input;
+            }
+
+            terms.push((
+                (queued.proof.a * r).to_affine(),
+                G2Prepared::from(queued.proof.b),
+            ));
+            terms.push(((acc * r).to_affine(), neg_gamma_g2.clone()));
+            terms.push(((queued.proof.c * r).to_affine(), neg_delta_g2.clone()));
+
+            r_sum += r;
+        }
+
+        let lhs = Bls12::multi_miller_loop(
+            &terms.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>(),
+        )
+        .final_exponentiation();
+
+        lhs == alpha_beta * r_sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::{Circuit, ConstraintSystem, SynthesisError};
+
+    // A trivial "prove knowledge of a, b such that a * b = c, with c public" circuit, used only
+    // to produce real Groth16 params/proofs for these tests. `BatchValidator` is meant to batch
+    // the four sapling circuits, but those need the real bundled params to set up - the batching math itself doesn't care which circuit it's folding, so a
+    // throwaway circuit exercises the same `validate_queue` code path.
+    struct MultiplyDemo {
+        a: Option<Scalar>,
+        b: Option<Scalar>,
+    }
+
+    impl Circuit<Scalar> for MultiplyDemo {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let a = cs.alloc(|| "a", || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+            let b = cs.alloc(|| "b", || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+            let c = cs.alloc_input(
+                || "c",
+                || {
+                    let a = self.a.ok_or(SynthesisError::AssignmentMissing)?;
+                    let b = self.b.ok_or(SynthesisError::AssignmentMissing)?;
+                    Ok(a * b)
+                },
+            )?;
+
+            cs.enforce(|| "a * b = c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+            Ok(())
+        }
+    }
+
+    fn test_params() -> groth16::Parameters<Bls12> {
+        groth16::generate_random_parameters::<Bls12, _, _>(
+            MultiplyDemo { a: None, b: None },
+            &mut OsRng,
+        )
+        .expect("trusted setup for test circuit")
+    }
+
+    // Generate a real proof (and its one public input) for `a * b = c`.
+    fn test_proof(
+        params: &groth16::Parameters<Bls12>,
+        a: u64,
+        b: u64,
+    ) -> (groth16::Proof<Bls12>, Vec<Scalar>) {
+        let a = Scalar::from(a);
+        let b = Scalar::from(b);
+
+        let proof = groth16::create_random_proof(
+            MultiplyDemo {
+                a: Some(a),
+                b: Some(b),
+            },
+            params,
+            &mut OsRng,
+        )
+        .expect("proof generation for test circuit");
+
+        (proof, vec![a * b])
+    }
+
+    #[test]
+    fn validate_queue_accepts_a_valid_batch() {
+        let params = test_params();
+        let mut validator = BatchValidator::new();
+
+        let (proof1, inputs1) = test_proof(&params, 3, 5);
+        let (proof2, inputs2) = test_proof(&params, 7, 11);
+        validator.queue_spend(proof1, inputs1);
+        validator.queue_spend(proof2, inputs2);
+
+        assert!(BatchValidator::validate_queue(
+            &validator.spend_queue,
+            &params.vk
+        ));
+    }
+
+    #[test]
+    fn validate_queue_rejects_a_tampered_proof() {
+        let params = test_params();
+        let mut validator = BatchValidator::new();
+
+        let (proof1, inputs1) = test_proof(&params, 3, 5);
+        let (proof2, _) = test_proof(&params, 7, 11);
+        validator.queue_spend(proof1, inputs1);
+        // Pair proof2 with the wrong public input, simulating a tampered/mismatched proof.
+        validator.queue_spend(proof2, vec![Scalar::from(999)]);
+
+        assert!(!BatchValidator::validate_queue(
+            &validator.spend_queue,
+            &params.vk
+        ));
+    }
+
+    #[test]
+    fn validate_queue_rejects_wrong_length_public_inputs() {
+        let params = test_params();
+        let mut validator = BatchValidator::new();
+
+        let (proof, _) = test_proof(&params, 3, 5);
+        validator.queue_spend(proof, vec![]);
+
+        assert!(!BatchValidator::validate_queue(
+            &validator.spend_queue,
+            &params.vk
+        ));
+    }
+
+    #[test]
+    fn validate_queue_accepts_an_empty_queue() {
+        let params = test_params();
+
+        assert!(BatchValidator::validate_queue(&[], &params.vk));
+    }
+}
@@ -0,0 +1,40 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use blake2b_simd::{Params, State};
+use std::io::{self, Read};
+
+/// Wraps a reader and feeds every byte that passes through it into a
+/// running BLAKE2b-512 hash, so the caller can verify the integrity of a
+/// stream (such as a sapling params file) without buffering the whole
+/// thing in memory first.
+pub struct HashReader<R: Read> {
+    inner: R,
+    hasher: State,
+}
+
+impl<R: Read> HashReader<R> {
+    pub fn new(inner: R) -> Self {
+        HashReader {
+            inner,
+            hasher: Params::new().hash_length(64).to_state(),
+        }
+    }
+
+    /// Destroy this reader and return the hex-encoded digest of everything
+    /// that was read through it.
+    pub fn into_hash(self) -> String {
+        self.hasher.finalize().to_hex().to_string()
+    }
+}
+
+impl<R: Read> Read for HashReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        if bytes_read > 0 {
+            self.hasher.update(&buf[..bytes_read]);
+        }
+        Ok(bytes_read)
+    }
+}
@@ -5,11 +5,16 @@
 #[macro_use]
 extern crate lazy_static;
 
-use bellman::groth16;
-use bls12_381::Bls12;
+use bellman::{groth16, Circuit};
+use bls12_381::{Bls12, Scalar};
+use std::io::{self, Read};
 
+mod hash_reader;
 mod serializing;
 
+use hash_reader::HashReader;
+
+pub mod batch_validator;
 pub mod errors;
 pub mod keys;
 pub mod merkle_note;
@@ -17,8 +22,10 @@ pub mod merkle_note_hash;
 pub mod mining;
 pub mod nacl;
 pub mod note;
+pub mod parameters;
 pub mod primitives;
 pub mod proofs;
+pub mod provers;
 pub mod receiving;
 pub mod rolling_filter;
 pub mod sapling_bls12;
@@ -26,11 +33,17 @@ pub mod spending;
 pub mod transaction;
 pub mod witness;
 pub use {
+    batch_validator::BatchValidator,
     keys::{IncomingViewKey, OutgoingViewKey, PublicAddress, SaplingKey, ViewKeys},
     merkle_note::MerkleNote,
     merkle_note_hash::MerkleNoteHash,
     note::Note,
+    parameters::{CreateAssetParameters, MintAssetParameters, ReceiptParameters, SpendParameters},
     primitives::asset_type::AssetType,
+    provers::{
+        BellmanProver, CreateAssetProver, MintAssetProver, OutputProver, ProvingError,
+        SpendProver,
+    },
     receiving::{ReceiptParams, ReceiptProof},
     spending::{SpendParams, SpendProof},
     transaction::{ProposedTransaction, Transaction},
@@ -43,19 +56,68 @@ pub(crate) mod test_util; // I'm not sure if this is the right way to publish th
 // methods on it to do the actual work.
 //
 // spend and output are two arithmetic circuits for use in zksnark calculations provided by Bellman.
-// Though the *_params have a verifying key on them, they are not the prepared verifying keys,
-// so we store the prepared keys separately at the time of loading the params.
+// Each circuit's Groth16 parameters and prepared verifying key are bundled together in their own
+// newtype (see `parameters`), and each newtype can be read on its own and handed to a prover
+// directly. A `Sapling` itself always holds all four: `load` and `load_from` read every circuit's
+// params, and there is no constructor that takes only some of them.
 //
 // The values are all loaded from a file in serialized form.
 pub struct Sapling {
-    spend_params: groth16::Parameters<Bls12>,
-    receipt_params: groth16::Parameters<Bls12>,
-    create_asset_params: groth16::Parameters<Bls12>,
-    mint_asset_params: groth16::Parameters<Bls12>,
-    spend_verifying_key: groth16::PreparedVerifyingKey<Bls12>,
-    receipt_verifying_key: groth16::PreparedVerifyingKey<Bls12>,
-    create_asset_verifying_key: groth16::PreparedVerifyingKey<Bls12>,
-    mint_asset_verifying_key: groth16::PreparedVerifyingKey<Bls12>,
+    spend_params: SpendParameters,
+    receipt_params: ReceiptParameters,
+    create_asset_params: CreateAssetParameters,
+    mint_asset_params: MintAssetParameters,
+    // Whether to immediately verify a proof against its own public inputs right after generating
+    // it. This catches a miscompiled circuit or bad witness at proving time instead of letting an
+    // invalid proof surface much later at block validation, at the cost of an extra verification
+    // per proof - so it's cheap to flip off in hot production paths. Defaults to on in debug/test
+    // builds and off in release builds.
+    //
+    // IMPORTANT: this flag is only honored by proofs generated through `prove_spend`/
+    // `prove_spend_with` (and the output/create-asset/mint-asset equivalents) below. Transaction
+    // construction code (`spending`/`receiving`/`transaction`) MUST call those instead of invoking `BellmanProver`/`groth16::create_random_proof` directly, or
+    // this check silently never runs for real transactions.
+    verify_on_prove: bool,
+}
+
+// Expected BLAKE2b-512 digests (hex-encoded) of the bundled sapling params, or `None` if no
+// known-good digest is available yet. When `Some`, the digest is checked against the bytes
+// actually streamed through `groth16::Parameters::read` at load time, so a corrupted or
+// substituted params file is rejected instead of silently accepted. `load_params` treats `None`
+// as "integrity checking isn't wired up for this circuit yet" and skips the comparison (with a
+// loud runtime warning) rather than failing every load - a missing known-good hash should not
+// turn the default constructor into an unconditional panic.
+//
+// All four are currently `None`, so every params file, bundled or on-disk, is accepted without a
+// digest comparison (after the warning `check_params_digest` prints).
+const SPEND_PARAMS_HASH: Option<&str> = None;
+const RECEIPT_PARAMS_HASH: Option<&str> = None;
+const CREATE_ASSET_PARAMS_HASH: Option<&str> = None;
+const MINT_ASSET_PARAMS_HASH: Option<&str> = None;
+
+/// Compare a just-computed params digest against the known-good one for its circuit, if any is
+/// configured. Pulled out of `load_params` so the comparison itself - the only part of the
+/// integrity check that doesn't require megabytes of real params to exercise - can be unit
+/// tested on its own.
+fn check_params_digest(digest: &str, expected_hash: Option<&str>) -> io::Result<()> {
+    match expected_hash {
+        Some(expected_hash) if digest == expected_hash => Ok(()),
+        Some(expected_hash) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "sapling params integrity check failed: expected hash {}, got {}",
+                expected_hash, digest
+            ),
+        )),
+        None => {
+            eprintln!(
+                "warning: no known-good BLAKE2b-512 digest configured for these sapling params \
+                 (got {}); skipping the integrity check",
+                digest
+            );
+            Ok(())
+        }
+    }
 }
 
 impl Sapling {
@@ -69,34 +131,285 @@ impl Sapling {
         let create_asset_bytes = include_bytes!("sapling_params/sapling-create-asset.params");
         let mint_asset_bytes = include_bytes!("sapling_params/sapling-mint-asset.params");
 
-        let spend_params = Sapling::load_params(&spend_bytes[..]);
-        let receipt_params = Sapling::load_params(&receipt_bytes[..]);
-        let create_asset_params = Sapling::load_params(&create_asset_bytes[..]);
-        let mint_asset_params = Sapling::load_params(&mint_asset_bytes[..]);
-
-        let spend_vk = groth16::prepare_verifying_key(&spend_params.vk);
-        let receipt_vk = groth16::prepare_verifying_key(&receipt_params.vk);
-        let create_asset_vk = groth16::prepare_verifying_key(&create_asset_params.vk);
-        let mint_asset_vk = groth16::prepare_verifying_key(&mint_asset_params.vk);
-
-        Sapling {
-            spend_verifying_key: spend_vk,
-            receipt_verifying_key: receipt_vk,
-            create_asset_verifying_key: create_asset_vk,
-            mint_asset_verifying_key: mint_asset_vk,
-            spend_params,
-            receipt_params,
-            create_asset_params,
-            mint_asset_params,
+        Sapling::load_from(
+            &spend_bytes[..],
+            &receipt_bytes[..],
+            &create_asset_bytes[..],
+            &mint_asset_bytes[..],
+        )
+        .expect("failed to load bundled sapling params")
+    }
+
+    /// Load sapling parameters from the given readers, computing the BLAKE2b-512 digest of each
+    /// one and comparing it against the hardcoded hash for its circuit, if one is configured,
+    /// before accepting it. This lets callers point at on-disk params (instead of the bundled ones)
+    /// with the same integrity check as `load`. No circuit has a hash configured at present (see
+    /// `SPEND_PARAMS_HASH` and its siblings), so no digest is compared and every params file that
+    /// parses is accepted.
+    pub fn load_from<R: Read>(
+        spend_bytes: R,
+        receipt_bytes: R,
+        create_asset_bytes: R,
+        mint_asset_bytes: R,
+    ) -> io::Result<Self> {
+        let spend_params = Sapling::load_params(spend_bytes, SPEND_PARAMS_HASH)?;
+        let receipt_params = Sapling::load_params(receipt_bytes, RECEIPT_PARAMS_HASH)?;
+        let create_asset_params =
+            Sapling::load_params(create_asset_bytes, CREATE_ASSET_PARAMS_HASH)?;
+        let mint_asset_params = Sapling::load_params(mint_asset_bytes, MINT_ASSET_PARAMS_HASH)?;
+
+        Ok(Sapling {
+            spend_params: SpendParameters::from_parameters(spend_params),
+            receipt_params: ReceiptParameters::from_parameters(receipt_params),
+            create_asset_params: CreateAssetParameters::from_parameters(create_asset_params),
+            mint_asset_params: MintAssetParameters::from_parameters(mint_asset_params),
+            verify_on_prove: cfg!(debug_assertions),
+        })
+    }
+
+    /// Whether proofs are verified against their own public inputs immediately after generation.
+    pub fn verify_on_prove(&self) -> bool {
+        self.verify_on_prove
+    }
+
+    pub fn set_verify_on_prove(&mut self, verify_on_prove: bool) {
+        self.verify_on_prove = verify_on_prove;
+    }
+
+    /// Generate a spend proof with the default in-process Bellman prover, and, if
+    /// `verify_on_prove` is enabled, check it against `public_inputs` before returning it.
+    pub fn prove_spend<C: Circuit<Scalar>>(
+        &self,
+        circuit: C,
+        public_inputs: &[Scalar],
+    ) -> Result<groth16::Proof<Bls12>, ProvingError> {
+        self.prove_spend_with(&BellmanProver, circuit, public_inputs)
+    }
+
+    /// Generate a spend proof with a caller-supplied `SpendProver` (a hardware signer, a remote
+    /// proving service, a test double, ...), and, if `verify_on_prove` is enabled, check it
+    /// against `public_inputs` before returning it.
+    pub fn prove_spend_with<P, C>(
+        &self,
+        prover: &P,
+        circuit: C,
+        public_inputs: &[Scalar],
+    ) -> Result<groth16::Proof<Bls12>, ProvingError>
+    where
+        P: SpendProver<Proof = groth16::Proof<Bls12>>,
+        C: Circuit<Scalar>,
+    {
+        let proof = prover.prove(&self.spend_params, circuit)?;
+        self.verify_if_enabled(self.spend_verifying_key(), &proof, public_inputs)?;
+        Ok(proof)
+    }
+
+    /// Generate an output proof with the default in-process Bellman prover, and, if
+    /// `verify_on_prove` is enabled, check it against `public_inputs` before returning it.
+    pub fn prove_output<C: Circuit<Scalar>>(
+        &self,
+        circuit: C,
+        public_inputs: &[Scalar],
+    ) -> Result<groth16::Proof<Bls12>, ProvingError> {
+        self.prove_output_with(&BellmanProver, circuit, public_inputs)
+    }
+
+    /// Generate an output proof with a caller-supplied `OutputProver`, and, if `verify_on_prove`
+    /// is enabled, check it against `public_inputs` before returning it.
+    pub fn prove_output_with<P, C>(
+        &self,
+        prover: &P,
+        circuit: C,
+        public_inputs: &[Scalar],
+    ) -> Result<groth16::Proof<Bls12>, ProvingError>
+    where
+        P: OutputProver<Proof = groth16::Proof<Bls12>>,
+        C: Circuit<Scalar>,
+    {
+        let proof = prover.prove(&self.receipt_params, circuit)?;
+        self.verify_if_enabled(self.receipt_verifying_key(), &proof, public_inputs)?;
+        Ok(proof)
+    }
+
+    /// Generate a create-asset proof with the default in-process Bellman prover, and, if
+    /// `verify_on_prove` is enabled, check it against `public_inputs` before returning it.
+    pub fn prove_create_asset<C: Circuit<Scalar>>(
+        &self,
+        circuit: C,
+        public_inputs: &[Scalar],
+    ) -> Result<groth16::Proof<Bls12>, ProvingError> {
+        self.prove_create_asset_with(&BellmanProver, circuit, public_inputs)
+    }
+
+    /// Generate a create-asset proof with a caller-supplied `CreateAssetProver`, and, if
+    /// `verify_on_prove` is enabled, check it against `public_inputs` before returning it.
+    pub fn prove_create_asset_with<P, C>(
+        &self,
+        prover: &P,
+        circuit: C,
+        public_inputs: &[Scalar],
+    ) -> Result<groth16::Proof<Bls12>, ProvingError>
+    where
+        P: CreateAssetProver<Proof = groth16::Proof<Bls12>>,
+        C: Circuit<Scalar>,
+    {
+        let proof = prover.prove(&self.create_asset_params, circuit)?;
+        self.verify_if_enabled(self.create_asset_verifying_key(), &proof, public_inputs)?;
+        Ok(proof)
+    }
+
+    /// Generate a mint-asset proof with the default in-process Bellman prover, and, if
+    /// `verify_on_prove` is enabled, check it against `public_inputs` before returning it.
+    pub fn prove_mint_asset<C: Circuit<Scalar>>(
+        &self,
+        circuit: C,
+        public_inputs: &[Scalar],
+    ) -> Result<groth16::Proof<Bls12>, ProvingError> {
+        self.prove_mint_asset_with(&BellmanProver, circuit, public_inputs)
+    }
+
+    /// Generate a mint-asset proof with a caller-supplied `MintAssetProver`, and, if
+    /// `verify_on_prove` is enabled, check it against `public_inputs` before returning it.
+    pub fn prove_mint_asset_with<P, C>(
+        &self,
+        prover: &P,
+        circuit: C,
+        public_inputs: &[Scalar],
+    ) -> Result<groth16::Proof<Bls12>, ProvingError>
+    where
+        P: MintAssetProver<Proof = groth16::Proof<Bls12>>,
+        C: Circuit<Scalar>,
+    {
+        let proof = prover.prove(&self.mint_asset_params, circuit)?;
+        self.verify_if_enabled(self.mint_asset_verifying_key(), &proof, public_inputs)?;
+        Ok(proof)
+    }
+
+    fn verify_if_enabled(
+        &self,
+        vk: &groth16::PreparedVerifyingKey<Bls12>,
+        proof: &groth16::Proof<Bls12>,
+        public_inputs: &[Scalar],
+    ) -> Result<(), ProvingError> {
+        if !self.verify_on_prove {
+            return Ok(());
         }
+
+        // `verify_proof` returns `Ok(())` for a valid proof and `Err(VerificationError::..)`
+        // otherwise - there's no boolean to branch on, so the `?` (via `From<VerificationError>
+        // for ProvingError`) does the whole job.
+        groth16::verify_proof(vk, proof, public_inputs)?;
+        Ok(())
     }
 
-    /// Load sapling parameters from a provided filename. The parameters are huge and take a
-    /// couple seconds to load. They primarily contain the "toxic waste" for a specific sapling
-    /// curve.
+    /// Load sapling parameters from a provided reader, verifying their integrity along the way.
+    /// The parameters are huge and take a couple seconds to load. They primarily contain the
+    /// "toxic waste" for a specific sapling curve.
+    ///
+    /// Every byte read from `reader` is hashed with BLAKE2b-512 as it streams into
+    /// `groth16::Parameters::read`. If `expected_hash` is `Some`, the finalized digest must match
+    /// it or the params are rejected even though parsing succeeded, since a corrupted or
+    /// substituted file could otherwise still deserialize into a (wrong) valid-looking
+    /// `Parameters` value. If `expected_hash` is `None` (no known-good digest wired up yet for
+    /// this circuit), the check is skipped with a loud runtime warning instead of failing.
     ///
     /// NOTE: If this is stupidly slow for you, try compiling in --release mode
-    fn load_params(bytes: &[u8]) -> groth16::Parameters<Bls12> {
-        groth16::Parameters::read(bytes, false).unwrap()
+    fn load_params<R: Read>(
+        reader: R,
+        expected_hash: Option<&str>,
+    ) -> io::Result<groth16::Parameters<Bls12>> {
+        let mut hash_reader = HashReader::new(reader);
+        let params = groth16::Parameters::read(&mut hash_reader, false)?;
+
+        let digest = hash_reader.into_hash();
+        check_params_digest(&digest, expected_hash)?;
+
+        Ok(params)
+    }
+
+    pub(crate) fn spend_verifying_key(&self) -> &groth16::PreparedVerifyingKey<Bls12> {
+        self.spend_params.verifying_key()
+    }
+
+    pub(crate) fn receipt_verifying_key(&self) -> &groth16::PreparedVerifyingKey<Bls12> {
+        self.receipt_params.verifying_key()
+    }
+
+    pub(crate) fn create_asset_verifying_key(&self) -> &groth16::PreparedVerifyingKey<Bls12> {
+        self.create_asset_params.verifying_key()
+    }
+
+    pub(crate) fn mint_asset_verifying_key(&self) -> &groth16::PreparedVerifyingKey<Bls12> {
+        self.mint_asset_params.verifying_key()
+    }
+
+    // Raw (public) verifying keys, for code elsewhere in this crate that needs to read `VerifyingKey`
+    // fields `bellman` doesn't expose on the already-`PreparedVerifyingKey` (e.g. `BatchValidator`,
+    // which builds its own prepared terms so it can batch them across proofs).
+    pub(crate) fn spend_verifying_key_raw(&self) -> &groth16::VerifyingKey<Bls12> {
+        &self.spend_params.params().vk
+    }
+
+    pub(crate) fn receipt_verifying_key_raw(&self) -> &groth16::VerifyingKey<Bls12> {
+        &self.receipt_params.params().vk
+    }
+
+    pub(crate) fn create_asset_verifying_key_raw(&self) -> &groth16::VerifyingKey<Bls12> {
+        &self.create_asset_params.params().vk
+    }
+
+    pub(crate) fn mint_asset_verifying_key_raw(&self) -> &groth16::VerifyingKey<Bls12> {
+        &self.mint_asset_params.params().vk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_params_digest, HashReader, Sapling};
+    use std::io::Read;
+
+    // Regression test for the integrity-check hashes in `load_params`: if a bundled params file
+    // fails to parse, or its `*_PARAMS_HASH` constant is `Some` and doesn't match it, this panics
+    // via `Sapling::load`'s `.expect(..)` instead of failing quietly somewhere downstream.
+    //
+    // Ignored by default because it parses all four bundled params files, which takes seconds
+    // (see `load_params`). The integrity-check logic itself (digest computation and comparison)
+    // is covered without the real params by `hash_reader_digests_known_input` and
+    // `check_params_digest_*` below.
+    #[test]
+    #[ignore = "parses all four bundled sapling_params/*.params files, which is slow"]
+    fn test_load_bundled_params() {
+        Sapling::load();
+    }
+
+    // BLAKE2b-512 of b"ironfish sapling params hash_reader test fixture", computed independently
+    // with `b2sum -l 512` - exercises the same hashing `load_params` relies on, without loading
+    // the real sapling params files.
+    const KNOWN_INPUT: &[u8] = b"ironfish sapling params hash_reader test fixture";
+    const KNOWN_INPUT_DIGEST: &str = "ea9663874d512a6f23967d1dbee1ff4f81e8c215c0562f1f0385c3bb0461378f18e2fa589c5801340ba0acd61299320b05edfd9f7f9a95b17195bf25b21a8567";
+
+    #[test]
+    fn hash_reader_digests_known_input() {
+        let mut reader = HashReader::new(KNOWN_INPUT);
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+
+        assert_eq!(buf, KNOWN_INPUT);
+        assert_eq!(reader.into_hash(), KNOWN_INPUT_DIGEST);
+    }
+
+    #[test]
+    fn check_params_digest_accepts_matching_hash() {
+        assert!(check_params_digest(KNOWN_INPUT_DIGEST, Some(KNOWN_INPUT_DIGEST)).is_ok());
+    }
+
+    #[test]
+    fn check_params_digest_rejects_mismatched_hash() {
+        assert!(check_params_digest(KNOWN_INPUT_DIGEST, Some("not the right digest")).is_err());
+    }
+
+    #[test]
+    fn check_params_digest_skips_when_no_expected_hash_configured() {
+        assert!(check_params_digest(KNOWN_INPUT_DIGEST, None).is_ok());
     }
 }
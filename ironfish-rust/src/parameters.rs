@@ -0,0 +1,64 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Per-circuit Groth16 parameter newtypes. Splitting these out of `Sapling` lets a caller load
+// only the parameter sets it actually needs (a wallet that only spends doesn't have to pull in
+// the mint-asset params too), and gives each circuit's params and prepared verifying key a home
+// that doesn't depend on the other three circuits existing.
+
+use std::io::{self, Read};
+
+use bellman::groth16;
+use bls12_381::Bls12;
+
+macro_rules! circuit_parameters {
+    ($name:ident, $doc:expr) => {
+        #[doc = $doc]
+        pub struct $name {
+            groth16_params: groth16::Parameters<Bls12>,
+            groth16_vk: groth16::PreparedVerifyingKey<Bls12>,
+        }
+
+        impl $name {
+            /// Parse Groth16 parameters for this circuit out of `reader`. `verify_point_encodings`
+            /// is forwarded to `groth16::Parameters::read` as-is. No integrity check against a
+            /// known-good hash is made here; that check lives in `Sapling::load_params`, which
+            /// streams the params through a (crate-private) `HashReader`.
+            pub fn read<R: Read>(reader: R, verify_point_encodings: bool) -> io::Result<Self> {
+                let groth16_params = groth16::Parameters::read(reader, verify_point_encodings)?;
+                Ok(Self::from_parameters(groth16_params))
+            }
+
+            pub(crate) fn from_parameters(groth16_params: groth16::Parameters<Bls12>) -> Self {
+                let groth16_vk = groth16::prepare_verifying_key(&groth16_params.vk);
+                Self {
+                    groth16_params,
+                    groth16_vk,
+                }
+            }
+
+            pub fn params(&self) -> &groth16::Parameters<Bls12> {
+                &self.groth16_params
+            }
+
+            pub fn verifying_key(&self) -> &groth16::PreparedVerifyingKey<Bls12> {
+                &self.groth16_vk
+            }
+        }
+    };
+}
+
+circuit_parameters!(SpendParameters, "Groth16 parameters for the spend circuit.");
+circuit_parameters!(
+    ReceiptParameters,
+    "Groth16 parameters for the output (receipt) circuit."
+);
+circuit_parameters!(
+    CreateAssetParameters,
+    "Groth16 parameters for the create-asset circuit."
+);
+circuit_parameters!(
+    MintAssetParameters,
+    "Groth16 parameters for the mint-asset circuit."
+);
@@ -1,12 +1,10 @@
-// TODO: Decide on a name?
-// CreateAssetNote?
-// AssetNote?
-// What's less confusing when talking about it and trying to differentiate
-// between a regular "Note"
-
-use std::slice;
+use std::{
+    io::{self, Read, Write},
+    slice,
+};
 
 use bls12_381::Scalar;
+use byteorder::{LittleEndian, WriteBytesExt};
 use group::Curve;
 use rand::{thread_rng, Rng};
 use zcash_primitives::{
@@ -14,7 +12,18 @@ use zcash_primitives::{
     sapling::pedersen_hash,
 };
 
-use crate::primitives::asset_type::AssetInfo;
+use crate::{
+    keys::{OutgoingViewKey, SaplingKey},
+    primitives::asset_type::AssetInfo,
+};
+
+// Personalization used when hashing a create-asset note's nullifier, analogous to the
+// personalization `Note::nullifier` uses for ordinary notes.
+const CREATE_ASSET_NULLIFIER_PERSONALIZATION: &[u8; 8] = b"IFNFCrtA";
+
+// Personalization used when deriving the per-note key for `encrypt_for_sender`/
+// `decrypt_for_sender`, analogous to `CREATE_ASSET_NULLIFIER_PERSONALIZATION` above.
+const CREATE_ASSET_OUTGOING_KEY_PERSONALIZATION: &[u8; 8] = b"IFNFCrtO";
 
 /// A create asset note represents an asset in the owner's "account"
 /// Expected API:
@@ -24,36 +33,42 @@ use crate::primitives::asset_type::AssetInfo;
 pub struct CreateAssetNote {
     pub(crate) asset_info: AssetInfo,
     pub(crate) randomness: jubjub::Fr,
+    // The Pedersen commitment preimage hashes to the same point for a given (asset_info,
+    // randomness) pair every time it's asked for, so compute it once here instead of on every
+    // call to `commitment_point`.
+    commitment_full_point: jubjub::SubgroupPoint,
 }
 
 impl CreateAssetNote {
-    // TODO: carry over all? fns from Note
     pub fn new(asset_info: AssetInfo) -> Self {
         let mut buffer = [0u8; 64];
         thread_rng().fill(&mut buffer[..]);
 
         let randomness: jubjub::Fr = jubjub::Fr::from_bytes_wide(&buffer);
+        let commitment_full_point = Self::compute_commitment_full_point(&asset_info, &randomness);
 
         Self {
             asset_info,
             randomness,
+            commitment_full_point,
         }
     }
 
     pub fn commitment_point(&self) -> Scalar {
-        jubjub::ExtendedPoint::from(self.commitment_full_point())
+        jubjub::ExtendedPoint::from(self.commitment_full_point)
             .to_affine()
             .get_u()
     }
 
-    // TODO: Look into how many times this is called in the object's lifecycle
-    // and see if caching the preimage, hash, etc makes sense.
-    fn commitment_full_point(&self) -> jubjub::SubgroupPoint {
+    fn compute_commitment_full_point(
+        asset_info: &AssetInfo,
+        randomness: &jubjub::Fr,
+    ) -> jubjub::SubgroupPoint {
         let mut create_commitment_plaintext: Vec<u8> = vec![];
         create_commitment_plaintext.extend(GH_FIRST_BLOCK);
-        create_commitment_plaintext.extend(self.asset_info.name());
-        create_commitment_plaintext.extend(self.asset_info.public_address_bytes());
-        create_commitment_plaintext.extend(slice::from_ref(self.asset_info.nonce()));
+        create_commitment_plaintext.extend(asset_info.name());
+        create_commitment_plaintext.extend(asset_info.public_address_bytes());
+        create_commitment_plaintext.extend(slice::from_ref(asset_info.nonce()));
 
         let create_commitment_hash = pedersen_hash::pedersen_hash(
             pedersen_hash::Personalization::NoteCommitment,
@@ -62,6 +77,197 @@ impl CreateAssetNote {
                 .flat_map(|byte| (0..8).map(move |i| ((byte >> i) & 1) == 1)),
         );
 
-        create_commitment_hash + (NOTE_COMMITMENT_RANDOMNESS_GENERATOR * self.randomness)
+        create_commitment_hash + (NOTE_COMMITMENT_RANDOMNESS_GENERATOR * randomness)
+    }
+
+    /// Derive the nullifier for this created asset at the given position in the notes tree, so
+    /// that the asset it creates can later be referenced (and the note it lives in spent), the
+    /// same way `Note::nullifier` ties an ordinary note to a tree position and the spend
+    /// authority's nullifier-deriving key.
+    ///
+    /// This takes the full `SaplingKey` (the spend authority), not an `IncomingViewKey`: the
+    /// incoming viewing key is the shareable, audit/detection key, and a nullifier derived from
+    /// it could be computed by anyone holding that key, defeating spend unlinkability.
+    pub fn nullifier(&self, spender_key: &SaplingKey, position: u64) -> [u8; 32] {
+        let mut position_bytes = [0u8; 8];
+        (&mut position_bytes[..])
+            .write_u64::<LittleEndian>(position)
+            .unwrap();
+
+        let mut hasher = blake2s_simd::Params::new()
+            .hash_length(32)
+            .personal(CREATE_ASSET_NULLIFIER_PERSONALIZATION)
+            .to_state();
+        hasher.update(&spender_key.nullifier_deriving_key().to_bytes());
+        hasher.update(&self.commitment_point().to_bytes());
+        hasher.update(&position_bytes);
+
+        let mut nullifier = [0u8; 32];
+        nullifier.copy_from_slice(hasher.finalize().as_bytes());
+        nullifier
+    }
+
+    /// Encrypt this note so that it can be recovered by the owner's `IncomingViewKey`, mirroring
+    /// how `MerkleNote` wraps an encrypted `Note`.
+    ///
+    /// Like `Note::encrypt`, this takes the shared secret itself rather than an `IncomingViewKey`:
+    /// the Diffie-Hellman exchange against the recipient's key happens one level up, wherever this
+    /// note is being wrapped into a transaction output (mirroring `MerkleNote`, which derives the
+    /// shared secret once and passes it down to the note it's encrypting).
+    pub fn encrypt(&self, shared_secret: &[u8; 32]) -> Vec<u8> {
+        let mut plaintext = vec![];
+        self.write(&mut plaintext)
+            .expect("serialization to a Vec cannot fail");
+
+        crate::nacl::secretbox_seal(shared_secret, &plaintext)
+    }
+
+    /// Decrypt a `CreateAssetNote` that was encrypted with `encrypt`, using the shared secret the
+    /// owner's `IncomingViewKey` derives for this note's ephemeral key.
+    pub fn decrypt(shared_secret: &[u8; 32], ciphertext: &[u8]) -> io::Result<Self> {
+        let plaintext = crate::nacl::secretbox_open(shared_secret, ciphertext)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "note decryption failed"))?;
+
+        Self::read(&plaintext[..])
+    }
+
+    /// Re-encrypt this note so the sender can recover it later with their own `OutgoingViewKey`,
+    /// the same way `MerkleNote` produces an outgoing ciphertext for ordinary `Note`s.
+    ///
+    /// The secretbox key is a per-note key derived from `outgoing_view_key` and this note's own
+    /// commitment (see `outgoing_cipher_key`), not the raw `OutgoingViewKey` bytes - using the
+    /// view key directly as the key would reuse the same secretbox key for every note the account
+    /// ever creates.
+    pub fn encrypt_for_sender(&self, outgoing_view_key: &OutgoingViewKey) -> Vec<u8> {
+        let mut plaintext = vec![];
+        self.write(&mut plaintext)
+            .expect("serialization to a Vec cannot fail");
+
+        crate::nacl::secretbox_seal(&self.outgoing_cipher_key(outgoing_view_key), &plaintext)
+    }
+
+    /// Decrypt a `CreateAssetNote` that was encrypted with `encrypt_for_sender`, given the same
+    /// `OutgoingViewKey` and the note's commitment point (public once the note has been committed
+    /// to the tree, the same way a `MerkleNote`'s `cmu` is public).
+    pub fn decrypt_for_sender(
+        outgoing_view_key: &OutgoingViewKey,
+        commitment_point: &Scalar,
+        ciphertext: &[u8],
+    ) -> io::Result<Self> {
+        let key = Self::outgoing_cipher_key_for(outgoing_view_key, commitment_point);
+        let plaintext = crate::nacl::secretbox_open(&key, ciphertext)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "note decryption failed"))?;
+
+        Self::read(&plaintext[..])
+    }
+
+    /// Derive the per-note secretbox key used by `encrypt_for_sender`/`decrypt_for_sender`.
+    fn outgoing_cipher_key(&self, outgoing_view_key: &OutgoingViewKey) -> [u8; 32] {
+        Self::outgoing_cipher_key_for(outgoing_view_key, &self.commitment_point())
+    }
+
+    fn outgoing_cipher_key_for(
+        outgoing_view_key: &OutgoingViewKey,
+        commitment_point: &Scalar,
+    ) -> [u8; 32] {
+        let mut hasher = blake2s_simd::Params::new()
+            .hash_length(32)
+            .personal(CREATE_ASSET_OUTGOING_KEY_PERSONALIZATION)
+            .to_state();
+        hasher.update(&outgoing_view_key.view_key);
+        hasher.update(&commitment_point.to_bytes());
+
+        let mut key = [0u8; 32];
+        key.copy_from_slice(hasher.finalize().as_bytes());
+        key
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.asset_info.write(&mut writer)?;
+        writer.write_all(&self.randomness.to_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let asset_info = AssetInfo::read(&mut reader)?;
+
+        let mut randomness_bytes = [0u8; 32];
+        reader.read_exact(&mut randomness_bytes)?;
+        let randomness = Option::from(jubjub::Fr::from_bytes(&randomness_bytes))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid randomness"))?;
+
+        let commitment_full_point = Self::compute_commitment_full_point(&asset_info, &randomness);
+
+        Ok(Self {
+            asset_info,
+            randomness,
+            commitment_full_point,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_note() -> (SaplingKey, CreateAssetNote) {
+        let spender_key = SaplingKey::generate_key();
+        let asset_info = AssetInfo::new("Testcoin", spender_key.public_address())
+            .expect("failed to generate test asset info");
+
+        (spender_key, CreateAssetNote::new(asset_info))
+    }
+
+    #[test]
+    fn write_read_round_trips() {
+        let (_, note) = test_note();
+
+        let mut bytes = vec![];
+        note.write(&mut bytes).expect("write should succeed");
+
+        let round_tripped = CreateAssetNote::read(&bytes[..]).expect("read should succeed");
+
+        assert_eq!(note.commitment_point(), round_tripped.commitment_point());
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trips() {
+        let (_, note) = test_note();
+        let shared_secret = [7u8; 32];
+
+        let ciphertext = note.encrypt(&shared_secret);
+        let decrypted = CreateAssetNote::decrypt(&shared_secret, &ciphertext)
+            .expect("decryption should succeed with the matching shared secret");
+
+        assert_eq!(note.commitment_point(), decrypted.commitment_point());
+    }
+
+    #[test]
+    fn encrypt_decrypt_for_sender_round_trips() {
+        let (spender_key, note) = test_note();
+        let outgoing_view_key = spender_key.outgoing_view_key();
+
+        let ciphertext = note.encrypt_for_sender(outgoing_view_key);
+        let decrypted = CreateAssetNote::decrypt_for_sender(
+            outgoing_view_key,
+            &note.commitment_point(),
+            &ciphertext,
+        )
+        .expect("decryption should succeed with the matching outgoing view key and commitment");
+
+        assert_eq!(note.commitment_point(), decrypted.commitment_point());
+    }
+
+    #[test]
+    fn nullifier_is_deterministic_per_position() {
+        let (spender_key, note) = test_note();
+
+        let nullifier_a = note.nullifier(&spender_key, 0);
+        let nullifier_b = note.nullifier(&spender_key, 0);
+        let nullifier_at_other_position = note.nullifier(&spender_key, 1);
+
+        assert_eq!(nullifier_a, nullifier_b);
+        assert_ne!(nullifier_a, nullifier_at_other_position);
     }
 }
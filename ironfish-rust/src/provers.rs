@@ -0,0 +1,156 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Pluggable proving backends. `Sapling` only needs a set of `PreparedVerifyingKey`s to verify
+// proofs, but generating them is a separate concern - these traits let a caller swap the default
+// in-process Bellman prover for something else (a hardware signer, a remote proving service, a
+// test double that returns canned proofs) without touching the circuit-building code in
+// `proofs/`.
+
+use std::fmt;
+
+use bellman::{groth16, Circuit, SynthesisError, VerificationError};
+use bls12_381::{Bls12, Scalar};
+use rand::rngs::OsRng;
+
+use crate::parameters::{CreateAssetParameters, MintAssetParameters, ReceiptParameters, SpendParameters};
+
+/// Everything that can go wrong while proving, including the optional post-generation
+/// verification pass (see `Sapling::verify_on_prove`).
+#[derive(Debug)]
+pub enum ProvingError {
+    Synthesis(SynthesisError),
+    /// The proof was generated successfully but failed verification against its own public
+    /// inputs, meaning the circuit or witness was unsound.
+    VerificationFailed,
+}
+
+impl fmt::Display for ProvingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProvingError::Synthesis(e) => write!(f, "failed to generate proof: {}", e),
+            ProvingError::VerificationFailed => {
+                write!(f, "generated proof failed verification against its own public inputs")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProvingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProvingError::Synthesis(e) => Some(e),
+            ProvingError::VerificationFailed => None,
+        }
+    }
+}
+
+impl From<SynthesisError> for ProvingError {
+    fn from(e: SynthesisError) -> Self {
+        ProvingError::Synthesis(e)
+    }
+}
+
+impl From<VerificationError> for ProvingError {
+    fn from(_: VerificationError) -> Self {
+        ProvingError::VerificationFailed
+    }
+}
+
+pub trait SpendProver {
+    type Proof;
+
+    fn prove<C: Circuit<Scalar>>(
+        &self,
+        params: &SpendParameters,
+        circuit: C,
+    ) -> Result<Self::Proof, SynthesisError>;
+}
+
+pub trait OutputProver {
+    type Proof;
+
+    fn prove<C: Circuit<Scalar>>(
+        &self,
+        params: &ReceiptParameters,
+        circuit: C,
+    ) -> Result<Self::Proof, SynthesisError>;
+}
+
+pub trait CreateAssetProver {
+    type Proof;
+
+    fn prove<C: Circuit<Scalar>>(
+        &self,
+        params: &CreateAssetParameters,
+        circuit: C,
+    ) -> Result<Self::Proof, SynthesisError>;
+}
+
+pub trait MintAssetProver {
+    type Proof;
+
+    fn prove<C: Circuit<Scalar>>(
+        &self,
+        params: &MintAssetParameters,
+        circuit: C,
+    ) -> Result<Self::Proof, SynthesisError>;
+}
+
+/// The default prover: generates proofs in-process with Bellman, the same way `Sapling` always
+/// has.
+///
+/// Callers that want `Sapling::verify_on_prove` to actually run must go through
+/// `Sapling::prove_spend`/`prove_spend_with` (and the output/create-asset/mint-asset
+/// equivalents) rather than calling this prover directly - `BellmanProver::prove` only generates
+/// the proof, it doesn't check it against its public inputs.
+pub struct BellmanProver;
+
+impl SpendProver for BellmanProver {
+    type Proof = groth16::Proof<Bls12>;
+
+    fn prove<C: Circuit<Scalar>>(
+        &self,
+        params: &SpendParameters,
+        circuit: C,
+    ) -> Result<Self::Proof, SynthesisError> {
+        groth16::create_random_proof(circuit, params.params(), &mut OsRng)
+    }
+}
+
+impl OutputProver for BellmanProver {
+    type Proof = groth16::Proof<Bls12>;
+
+    fn prove<C: Circuit<Scalar>>(
+        &self,
+        params: &ReceiptParameters,
+        circuit: C,
+    ) -> Result<Self::Proof, SynthesisError> {
+        groth16::create_random_proof(circuit, params.params(), &mut OsRng)
+    }
+}
+
+impl CreateAssetProver for BellmanProver {
+    type Proof = groth16::Proof<Bls12>;
+
+    fn prove<C: Circuit<Scalar>>(
+        &self,
+        params: &CreateAssetParameters,
+        circuit: C,
+    ) -> Result<Self::Proof, SynthesisError> {
+        groth16::create_random_proof(circuit, params.params(), &mut OsRng)
+    }
+}
+
+impl MintAssetProver for BellmanProver {
+    type Proof = groth16::Proof<Bls12>;
+
+    fn prove<C: Circuit<Scalar>>(
+        &self,
+        params: &MintAssetParameters,
+        circuit: C,
+    ) -> Result<Self::Proof, SynthesisError> {
+        groth16::create_random_proof(circuit, params.params(), &mut OsRng)
+    }
+}